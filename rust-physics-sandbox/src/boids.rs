@@ -0,0 +1,156 @@
+use nalgebra::{Point3, Vector3};
+use std::collections::HashMap;
+
+const PERCEPTION_RADIUS: f32 = 3.0;
+const MIN_SEPARATION_DISTANCE: f32 = 1.0;
+const MAX_SPEED: f32 = 4.0;
+
+const COHESION_WEIGHT: f32 = 1.0;
+const ALIGNMENT_WEIGHT: f32 = 1.0;
+const SEPARATION_WEIGHT: f32 = 1.5;
+const GOAL_WEIGHT: f32 = 0.5;
+
+type CellKey = (i32, i32, i32);
+
+// A flock of simple boids (cohesion/alignment/separation) for emergent
+// crowd/swarm behavior, independent of the rigid body and fluid solvers.
+pub struct BoidSystem {
+    pub positions: Vec<Point3<f32>>,
+    pub velocities: Vec<Vector3<f32>>,
+    pub goal: Option<Point3<f32>>,
+}
+
+impl BoidSystem {
+    pub fn new() -> Self {
+        Self {
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            goal: None,
+        }
+    }
+
+    // Adds `count` boids in a small grid around (x, y, z) so they start
+    // slightly apart instead of coincident.
+    pub fn spawn(&mut self, x: f32, y: f32, z: f32, count: u32) {
+        let per_row = (count as f32).sqrt().ceil() as u32 + 1;
+        let spacing = 0.5;
+
+        for i in 0..count {
+            let row = (i / per_row) as f32;
+            let col = (i % per_row) as f32;
+            self.positions.push(Point3::new(
+                x + col * spacing,
+                y,
+                z + row * spacing,
+            ));
+            self.velocities.push(Vector3::zeros());
+        }
+    }
+
+    fn cell_key(position: &Point3<f32>) -> CellKey {
+        (
+            (position.x / PERCEPTION_RADIUS).floor() as i32,
+            (position.y / PERCEPTION_RADIUS).floor() as i32,
+            (position.z / PERCEPTION_RADIUS).floor() as i32,
+        )
+    }
+
+    // Builds a uniform spatial hash grid keyed by cell so neighbor queries
+    // stay near O(n) instead of the O(n^2) all-pairs scan.
+    fn build_grid(&self) -> HashMap<CellKey, Vec<usize>> {
+        let mut grid: HashMap<CellKey, Vec<usize>> = HashMap::new();
+        for (i, position) in self.positions.iter().enumerate() {
+            grid.entry(Self::cell_key(position)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn neighbors_of(&self, grid: &HashMap<CellKey, Vec<usize>>, index: usize) -> Vec<usize> {
+        let (cx, cy, cz) = Self::cell_key(&self.positions[index]);
+        let mut neighbors = Vec::new();
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(bucket) = grid.get(&(cx + dx, cy + dy, cz + dz)) else {
+                        continue;
+                    };
+                    for &other in bucket {
+                        if other == index {
+                            continue;
+                        }
+                        let distance = (self.positions[other] - self.positions[index]).norm();
+                        if distance < PERCEPTION_RADIUS {
+                            neighbors.push(other);
+                        }
+                    }
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        let grid = self.build_grid();
+        let mut new_velocities = self.velocities.clone();
+
+        for i in 0..self.positions.len() {
+            let neighbors = self.neighbors_of(&grid, i);
+
+            let mut steering = Vector3::zeros();
+
+            if !neighbors.is_empty() {
+                let mut mean_position = Vector3::zeros();
+                let mut mean_velocity = Vector3::zeros();
+                let mut separation = Vector3::zeros();
+
+                for &j in &neighbors {
+                    mean_position += self.positions[j].coords;
+                    mean_velocity += self.velocities[j];
+
+                    let offset = self.positions[i] - self.positions[j];
+                    let distance = offset.norm();
+                    if distance < MIN_SEPARATION_DISTANCE && distance > 1e-5 {
+                        separation += offset / distance / distance;
+                    }
+                }
+
+                let neighbor_count = neighbors.len() as f32;
+                mean_position /= neighbor_count;
+                mean_velocity /= neighbor_count;
+
+                let cohesion = mean_position - self.positions[i].coords;
+                let alignment = mean_velocity - self.velocities[i];
+
+                steering += cohesion * COHESION_WEIGHT;
+                steering += alignment * ALIGNMENT_WEIGHT;
+                steering += separation * SEPARATION_WEIGHT;
+            }
+
+            if let Some(goal) = self.goal {
+                steering += (goal - self.positions[i]) * GOAL_WEIGHT;
+            }
+
+            let mut velocity = self.velocities[i] + steering * dt;
+            let speed = velocity.norm();
+            if speed > MAX_SPEED {
+                velocity = velocity / speed * MAX_SPEED;
+            }
+
+            new_velocities[i] = velocity;
+        }
+
+        self.velocities = new_velocities;
+
+        for i in 0..self.positions.len() {
+            self.positions[i] += self.velocities[i] * dt;
+
+            // Reflect off the floor plane instead of letting boids burrow into it.
+            if self.positions[i].y < 0.0 {
+                self.positions[i].y = 0.0;
+                self.velocities[i].y = -self.velocities[i].y;
+            }
+        }
+    }
+}
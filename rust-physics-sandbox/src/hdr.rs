@@ -0,0 +1,492 @@
+use wgpu::util::DeviceExt;
+
+// Bloom textures run at half the surface resolution; the blur is a 9-tap
+// separable Gaussian (4 weighted taps either side of center) so quality
+// holds up fine even downsampled, and the blur passes stay cheap.
+const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct ExposureUniform {
+    exposure: f32,
+    bloom_enabled: f32,
+    _pad: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BlurDirectionUniform {
+    direction: [f32; 2],
+    _pad: [f32; 2],
+}
+
+// Renders into an `Rgba16Float` offscreen target instead of the sRGB
+// swapchain directly, so bright highlights (emissive `obj_type`s, specular
+// hotspots) don't hard-clip before tone mapping gets a chance to roll them off.
+// Owns a bright-pass + separable Gaussian blur bloom stage and composites
+// everything with ACES filmic tone mapping into the real surface texture.
+pub struct HdrPipeline {
+    width: u32,
+    height: u32,
+
+    hdr_texture: wgpu::Texture,
+    hdr_view: wgpu::TextureView,
+    source_sampler: wgpu::Sampler,
+    source_bind_group_layout: wgpu::BindGroupLayout,
+    hdr_source_bind_group: wgpu::BindGroup,
+
+    bright_texture: wgpu::Texture,
+    bright_bind_group: wgpu::BindGroup,
+    bright_pipeline: wgpu::RenderPipeline,
+
+    blur_texture_a: wgpu::Texture,
+    blur_texture_b: wgpu::Texture,
+    blur_bind_group_a: wgpu::BindGroup,
+    blur_bind_group_b: wgpu::BindGroup,
+    blur_direction_bind_group_layout: wgpu::BindGroupLayout,
+    blur_direction_bind_group_h: wgpu::BindGroup,
+    blur_direction_bind_group_v: wgpu::BindGroup,
+    blur_pipeline: wgpu::RenderPipeline,
+
+    bloom_bind_group_layout: wgpu::BindGroupLayout,
+    bloom_bind_group: wgpu::BindGroup,
+
+    exposure_buffer: wgpu::Buffer,
+    exposure_bind_group: wgpu::BindGroup,
+
+    tonemap_pipeline: wgpu::RenderPipeline,
+
+    exposure: f32,
+    bloom_enabled: bool,
+}
+
+impl HdrPipeline {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, surface_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("hdr.wgsl"));
+
+        let source_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("hdr_source_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let source_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("hdr_source_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let exposure_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("exposure_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bloom_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let blur_direction_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blur_direction_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let exposure_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("exposure_buffer"),
+            contents: bytemuck::cast_slice(&[ExposureUniform {
+                exposure: 1.0,
+                bloom_enabled: 1.0,
+                _pad: [0.0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let exposure_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("exposure_bind_group"),
+            layout: &exposure_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: exposure_buffer.as_entire_binding(),
+            }],
+        });
+
+        let blur_direction_buffer_h = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("blur_direction_h"),
+            contents: bytemuck::cast_slice(&[BlurDirectionUniform {
+                direction: [1.0 / (width.max(1) / 2).max(1) as f32, 0.0],
+                _pad: [0.0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let blur_direction_buffer_v = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("blur_direction_v"),
+            contents: bytemuck::cast_slice(&[BlurDirectionUniform {
+                direction: [0.0, 1.0 / (height.max(1) / 2).max(1) as f32],
+                _pad: [0.0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let blur_direction_bind_group_h = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blur_direction_bind_group_h"),
+            layout: &blur_direction_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: blur_direction_buffer_h.as_entire_binding(),
+            }],
+        });
+        let blur_direction_bind_group_v = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blur_direction_bind_group_v"),
+            layout: &blur_direction_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: blur_direction_buffer_v.as_entire_binding(),
+            }],
+        });
+
+        let bright_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bright_pipeline_layout"),
+            bind_group_layouts: &[&source_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let bright_pipeline = Self::fullscreen_pipeline(device, &shader, "fs_bright", &bright_pipeline_layout, BLOOM_FORMAT);
+
+        let blur_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blur_pipeline_layout"),
+            bind_group_layouts: &[&source_bind_group_layout, &blur_direction_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let blur_pipeline = Self::fullscreen_pipeline(device, &shader, "fs_blur", &blur_pipeline_layout, BLOOM_FORMAT);
+
+        let tonemap_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("tonemap_pipeline_layout"),
+            bind_group_layouts: &[&source_bind_group_layout, &exposure_bind_group_layout, &bloom_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let tonemap_pipeline = Self::fullscreen_pipeline(device, &shader, "fs_tonemap", &tonemap_pipeline_layout, surface_format);
+
+        let (
+            hdr_texture,
+            hdr_view,
+            hdr_source_bind_group,
+            bright_texture,
+            bright_bind_group,
+            blur_texture_a,
+            blur_texture_b,
+            blur_bind_group_a,
+            blur_bind_group_b,
+            bloom_bind_group,
+        ) = Self::create_targets(device, width, height, &source_sampler, &source_bind_group_layout, &bloom_bind_group_layout);
+
+        Self {
+            width,
+            height,
+            hdr_texture,
+            hdr_view,
+            source_sampler,
+            source_bind_group_layout,
+            hdr_source_bind_group,
+            bright_texture,
+            bright_bind_group,
+            bright_pipeline,
+            blur_texture_a,
+            blur_texture_b,
+            blur_bind_group_a,
+            blur_bind_group_b,
+            blur_direction_bind_group_layout,
+            blur_direction_bind_group_h,
+            blur_direction_bind_group_v,
+            blur_pipeline,
+            bloom_bind_group_layout,
+            bloom_bind_group,
+            exposure_buffer,
+            exposure_bind_group,
+            tonemap_pipeline,
+            exposure: 1.0,
+            bloom_enabled: true,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn create_targets(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        source_sampler: &wgpu::Sampler,
+        source_bind_group_layout: &wgpu::BindGroupLayout,
+        bloom_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> (
+        wgpu::Texture,
+        wgpu::TextureView,
+        wgpu::BindGroup,
+        wgpu::Texture,
+        wgpu::BindGroup,
+        wgpu::Texture,
+        wgpu::Texture,
+        wgpu::BindGroup,
+        wgpu::BindGroup,
+        wgpu::BindGroup,
+    ) {
+        let hdr_texture = Self::create_texture(device, "hdr_texture", width.max(1), height.max(1), wgpu::TextureFormat::Rgba16Float);
+        let hdr_view = hdr_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let hdr_source_bind_group = Self::create_source_bind_group(device, source_bind_group_layout, &hdr_view, source_sampler, "hdr_source_bind_group");
+
+        let bloom_width = (width / 2).max(1);
+        let bloom_height = (height / 2).max(1);
+
+        let bright_texture = Self::create_texture(device, "bloom_bright_texture", bloom_width, bloom_height, BLOOM_FORMAT);
+        let bright_view = bright_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bright_bind_group = Self::create_source_bind_group(device, source_bind_group_layout, &bright_view, source_sampler, "bloom_bright_bind_group");
+
+        let blur_texture_a = Self::create_texture(device, "bloom_blur_a", bloom_width, bloom_height, BLOOM_FORMAT);
+        let blur_view_a = blur_texture_a.create_view(&wgpu::TextureViewDescriptor::default());
+        let blur_bind_group_a = Self::create_source_bind_group(device, source_bind_group_layout, &blur_view_a, source_sampler, "bloom_blur_bind_group_a");
+
+        let blur_texture_b = Self::create_texture(device, "bloom_blur_b", bloom_width, bloom_height, BLOOM_FORMAT);
+        let blur_view_b = blur_texture_b.create_view(&wgpu::TextureViewDescriptor::default());
+        let blur_bind_group_b = Self::create_source_bind_group(device, source_bind_group_layout, &blur_view_b, source_sampler, "bloom_blur_bind_group_b");
+
+        let bloom_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bloom_bind_group"),
+            layout: bloom_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&blur_view_b) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(source_sampler) },
+            ],
+        });
+
+        (
+            hdr_texture,
+            hdr_view,
+            hdr_source_bind_group,
+            bright_texture,
+            bright_bind_group,
+            blur_texture_a,
+            blur_texture_b,
+            blur_bind_group_a,
+            blur_bind_group_b,
+            bloom_bind_group,
+        )
+    }
+
+    fn create_texture(device: &wgpu::Device, label: &str, width: u32, height: u32, format: wgpu::TextureFormat) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    fn create_source_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        label: &str,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+            ],
+        })
+    }
+
+    fn fullscreen_pipeline(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        fragment_entry_point: &str,
+        layout: &wgpu::PipelineLayout,
+        target_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(fragment_entry_point),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: fragment_entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    // Recreates the float texture and bloom chain at the new surface size.
+    // Called whenever the surface is reconfigured.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        let (
+            hdr_texture,
+            hdr_view,
+            hdr_source_bind_group,
+            bright_texture,
+            bright_bind_group,
+            blur_texture_a,
+            blur_texture_b,
+            blur_bind_group_a,
+            blur_bind_group_b,
+            bloom_bind_group,
+        ) = Self::create_targets(device, width, height, &self.source_sampler, &self.source_bind_group_layout, &self.bloom_bind_group_layout);
+
+        self.hdr_texture = hdr_texture;
+        self.hdr_view = hdr_view;
+        self.hdr_source_bind_group = hdr_source_bind_group;
+        self.bright_texture = bright_texture;
+        self.bright_bind_group = bright_bind_group;
+        self.blur_texture_a = blur_texture_a;
+        self.blur_texture_b = blur_texture_b;
+        self.blur_bind_group_a = blur_bind_group_a;
+        self.blur_bind_group_b = blur_bind_group_b;
+        self.bloom_bind_group = bloom_bind_group;
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.hdr_view
+    }
+
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure;
+    }
+
+    pub fn set_bloom_enabled(&mut self, enabled: bool) {
+        self.bloom_enabled = enabled;
+    }
+
+    // Runs the bright-pass + two-pass separable blur, then tone-maps the
+    // combined result into `target`. The main scene must already have been
+    // rendered into `self.view()` before this is called.
+    pub fn process(&self, device: &wgpu::Device, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        queue.write_buffer(
+            &self.exposure_buffer,
+            0,
+            bytemuck::cast_slice(&[ExposureUniform {
+                exposure: self.exposure,
+                bloom_enabled: if self.bloom_enabled { 1.0 } else { 0.0 },
+                _pad: [0.0; 2],
+            }]),
+        );
+
+        let bright_view = self.bright_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.fullscreen_pass(device, encoder, &self.bright_pipeline, &[&self.hdr_source_bind_group], &bright_view);
+
+        let blur_view_a = self.blur_texture_a.create_view(&wgpu::TextureViewDescriptor::default());
+        self.fullscreen_pass(device, encoder, &self.blur_pipeline, &[&self.bright_bind_group, &self.blur_direction_bind_group_h], &blur_view_a);
+
+        let blur_view_b = self.blur_texture_b.create_view(&wgpu::TextureViewDescriptor::default());
+        self.fullscreen_pass(device, encoder, &self.blur_pipeline, &[&self.blur_bind_group_a, &self.blur_direction_bind_group_v], &blur_view_b);
+
+        self.fullscreen_pass(
+            device,
+            encoder,
+            &self.tonemap_pipeline,
+            &[&self.hdr_source_bind_group, &self.exposure_bind_group, &self.bloom_bind_group],
+            target,
+        );
+    }
+
+    fn fullscreen_pass(
+        &self,
+        _device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        bind_groups: &[&wgpu::BindGroup],
+        target: &wgpu::TextureView,
+    ) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("hdr_fullscreen_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_pipeline(pipeline);
+        for (i, bind_group) in bind_groups.iter().enumerate() {
+            pass.set_bind_group(i as u32, bind_group, &[]);
+        }
+        pass.draw(0..3, 0..1);
+    }
+}
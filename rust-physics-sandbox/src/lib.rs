@@ -2,6 +2,10 @@ mod physics;
 mod render;
 mod utils;
 mod soft_body;
+mod boids;
+mod hdr;
+mod texture;
+mod mesh;
 
 use wasm_bindgen::prelude::*;
 use std::cell::RefCell;
@@ -16,6 +20,10 @@ struct SimCommand {
     x: Option<f32>,
     y: Option<f32>,
     z: Option<f32>,
+    nx: Option<f32>,
+    ny: Option<f32>,
+    nz: Option<f32>,
+    count: Option<u32>,
 }
 
 thread_local! {
@@ -101,6 +109,17 @@ impl Simulation {
                         "spawn_cloth" => self.physics.spawn_cloth(cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0), 10, 10),
                         "spawn_avalanche" => self.physics.spawn_avalanche(cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0)),
                         "spawn_tsunami" => self.physics.spawn_tsunami(cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0)),
+                        "spawn_one_way_platform" => self.physics.spawn_one_way_platform(
+                            cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0),
+                            cmd.nx.unwrap_or(0.0), cmd.ny.unwrap_or(1.0), cmd.nz.unwrap_or(0.0),
+                        ),
+                        "spawn_boids" => self.physics.spawn_boids(
+                            cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0),
+                            cmd.count.unwrap_or(20),
+                        ),
+                        "spawn_character" => {
+                            self.physics.spawn_character(cmd.x.unwrap_or(0.0), cmd.y.unwrap_or(5.0), cmd.z.unwrap_or(0.0));
+                        },
                         _ => log(&format!("Unknown command: {}", cmd.cmd)),
                     }
                 }
@@ -144,6 +163,51 @@ impl Simulation {
         self.physics.spawn_tsunami(x, y, z);
     }
 
+    pub fn spawn_one_way_platform(&mut self, x: f32, y: f32, z: f32, nx: f32, ny: f32, nz: f32) {
+        self.physics.spawn_one_way_platform(x, y, z, nx, ny, nz);
+    }
+
+    pub fn spawn_boids(&mut self, x: f32, y: f32, z: f32, count: u32) {
+        self.physics.spawn_boids(x, y, z, count);
+    }
+
+    // Despawns the body with this stable id (see `ObjectRegistry`). Returns
+    // false if `id` doesn't refer to a currently spawned body.
+    pub fn despawn(&mut self, id: u64) -> bool {
+        self.physics.despawn(id)
+    }
+
+    // Spawns a kinematic character avatar and returns its stable id.
+    pub fn spawn_character(&mut self, x: f32, y: f32, z: f32) -> u64 {
+        self.physics.spawn_character(x, y, z)
+    }
+
+    // Moves the character identified by `id` by the desired translation,
+    // corrected for walls, slopes, and small ledges. Returns true if the
+    // character ended the move grounded.
+    pub fn move_character(&mut self, id: u64, dx: f32, dy: f32, dz: f32) -> bool {
+        self.physics.move_character(id, dx, dy, dz)
+    }
+
+    // Captures a byte-for-byte snapshot of the simulation for save-states,
+    // rollback, or sending a full authoritative state from the orchestrator.
+    pub fn save_state(&self) -> Vec<u8> {
+        self.physics.serialize()
+    }
+
+    // Restores a snapshot produced by `save_state`. Returns false if `bytes`
+    // doesn't decode to a valid snapshot, leaving the simulation untouched.
+    pub fn load_state(&mut self, bytes: &[u8]) -> bool {
+        self.physics.deserialize(bytes)
+    }
+
+    // Drains collision/contact-force records accumulated since the last call,
+    // flattened as [kind, idx1, idx2, px, py, pz, magnitude] per event
+    // (kind: 0 = collision started, 1 = collision stopped, 2 = contact force).
+    pub fn drain_collision_events(&mut self) -> Vec<f32> {
+        self.physics.take_collision_events()
+    }
+
     pub fn get_first_object_y(&self) -> f32 {
         self.physics.get_first_object_y()
     }
@@ -153,7 +217,63 @@ impl Simulation {
             renderer.update_camera(dx, dy, zoom);
         }
     }
-    
+
+    // Reconfigures the surface and every size-dependent render target. Must
+    // be called whenever the canvas element is resized.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.resize(width, height);
+        }
+    }
+
+    // Sets the directional light used for shading.
+    pub fn set_light(&mut self, direction: [f32; 3], color: [f32; 3], ambient: f32) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_light(direction, color, ambient);
+        }
+    }
+
+    // Exposure multiplier applied before ACES tone mapping (1.0 = neutral).
+    pub fn set_exposure(&mut self, exposure: f32) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_exposure(exposure);
+        }
+    }
+
+    // Toggles the bright-pass + Gaussian blur bloom stage on the HDR target.
+    pub fn set_bloom_enabled(&mut self, enabled: bool) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_bloom_enabled(enabled);
+        }
+    }
+
+    // Decodes a PNG/JPEG atlas image, tiled into `tiles_per_row` equal tiles
+    // across its width, and uploads it to replace the placeholder texture.
+    pub fn load_atlas(&mut self, bytes: &[u8], tiles_per_row: u32) -> Result<(), JsValue> {
+        match &mut self.renderer {
+            Some(renderer) => renderer.load_atlas(bytes, tiles_per_row),
+            None => Ok(()),
+        }
+    }
+
+    // Parses an OBJ mesh and appends it to the mesh table, returning its
+    // mesh id. Pair with `set_mesh_for_type` to draw an `obj_type` with it
+    // instead of the default cube.
+    pub fn register_mesh(&mut self, obj_bytes: &[u8]) -> Result<u32, JsValue> {
+        match &mut self.renderer {
+            Some(renderer) => renderer.register_mesh(obj_bytes),
+            None => Err(JsValue::from_str("init_graphics must be called before register_mesh")),
+        }
+    }
+
+    // Routes every instance of `obj_type` to draw with `mesh_id` (from
+    // `register_mesh`) instead of the default cube.
+    pub fn set_mesh_for_type(&mut self, obj_type: u32, mesh_id: u32) {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_mesh_for_type(obj_type, mesh_id);
+        }
+    }
+
     pub fn handle_click(&mut self, x: f32, y: f32) {
         if let Some(renderer) = &self.renderer {
             let (origin, dir) = renderer.get_ray_from_screen(x, y);
@@ -163,4 +283,15 @@ impl Simulation {
             }
         }
     }
+
+    // Pixel-accurate GPU pick at the given screen coordinates, returning the
+    // instance index drawn this frame (same ordering as `get_render_data`),
+    // or `None` if nothing was drawn there. Exact where `handle_click`'s
+    // geometric raycast is ambiguous, at the cost of a frame of latency.
+    pub async fn pick(&self, x: f32, y: f32) -> Option<u32> {
+        match &self.renderer {
+            Some(renderer) => renderer.pick(x as u32, y as u32).await,
+            None => None,
+        }
+    }
 }
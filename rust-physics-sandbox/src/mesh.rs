@@ -0,0 +1,77 @@
+use wgpu::util::DeviceExt;
+use crate::render::Vertex;
+
+// A single drawable mesh: its own vertex/index buffers plus the index count
+// needed to issue its `draw_indexed` call. `Renderer` keeps these in a
+// `Vec<Mesh>` indexed by mesh id, with id 0 reserved for the built-in cube.
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+impl Mesh {
+    pub fn from_raw(device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_vertex_buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_index_buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self { vertex_buffer, index_buffer, index_count: indices.len() as u32 }
+    }
+
+    // Parses a single-object OBJ file into our `Vertex` layout. Missing
+    // normals/texcoords (OBJ doesn't require either) fall back to an
+    // up-facing normal and a zeroed UV rather than failing the import.
+    pub fn from_obj_bytes(device: &wgpu::Device, bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = std::io::BufReader::new(bytes);
+        let load_options = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+        let (models, _materials) = tobj::load_obj_buf(&mut reader, &load_options, |_| {
+            Err(tobj::LoadError::GenericFailure)
+        }).map_err(|e| e.to_string())?;
+
+        let model = models.first().ok_or("OBJ file contains no meshes")?;
+        let mesh = &model.mesh;
+        let has_normals = !mesh.normals.is_empty();
+        let has_tex_coords = !mesh.texcoords.is_empty();
+
+        let vertex_count = mesh.positions.len() / 3;
+        let mut vertices = Vec::with_capacity(vertex_count);
+        for i in 0..vertex_count {
+            let position = [mesh.positions[i * 3], mesh.positions[i * 3 + 1], mesh.positions[i * 3 + 2]];
+            let normal = if has_normals {
+                [mesh.normals[i * 3], mesh.normals[i * 3 + 1], mesh.normals[i * 3 + 2]]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            let tex_coords = if has_tex_coords {
+                [mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1]]
+            } else {
+                [0.0, 0.0]
+            };
+            vertices.push(Vertex { position, normal, tex_coords });
+        }
+
+        // Index buffers are drawn as Uint16 (see `Renderer`'s index format),
+        // so silently truncating a larger index would corrupt the geometry
+        // instead of failing loudly.
+        let mut indices = Vec::with_capacity(mesh.indices.len());
+        for &i in &mesh.indices {
+            let i: u16 = i
+                .try_into()
+                .map_err(|_| format!("OBJ mesh has {} vertices, which exceeds the {}-vertex limit", vertex_count, u16::MAX as usize + 1))?;
+            indices.push(i);
+        }
+        Ok(Self::from_raw(device, &vertices, &indices))
+    }
+}
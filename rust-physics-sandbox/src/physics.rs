@@ -1,8 +1,238 @@
 use rapier3d::prelude::*;
+use rapier3d::pipeline::{ContactModificationContext, EventHandler, PhysicsHooks};
+use rapier3d::geometry::ContactPair;
+use rapier3d::control::{CharacterAutostep, CharacterLength, KinematicCharacterController};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use salva3d::integrations::rapier::FluidsPipeline;
-use salva3d::object::{Fluid, FluidHandle};
-use nalgebra::Point3;
+use salva3d::coupling::{ColliderCouplingSet, ColliderSampling};
+use salva3d::object::{Boundary, BoundaryHandle, Fluid, FluidHandle};
+use nalgebra::{Point3, Vector3};
+use serde::{Deserialize, Serialize};
+use crate::boids::BoidSystem;
+
+thread_local! {
+    // Mirrors the `CMD_QUEUE` pattern in lib.rs: `EventHandler` requires
+    // `Send + Sync`, so we can't stash a `RefCell` buffer on the handler
+    // itself and instead collect into a thread-local queue that `step()`
+    // drains afterwards.
+    static COLLISION_EVENT_QUEUE: RefCell<Vec<f32>> = RefCell::new(Vec::new());
+}
+
+// Tags for the flattened [kind, idx1, idx2, px, py, pz, magnitude] records
+// pushed onto `COLLISION_EVENT_QUEUE`.
+const EVENT_COLLISION_STARTED: f32 = 0.0;
+const EVENT_COLLISION_STOPPED: f32 = 1.0;
+const EVENT_CONTACT_FORCE: f32 = 2.0;
+
+// Looks up the stable `ObjectRegistry` id for a collider's parent body so
+// event records survive despawn/respawn cycles, instead of the raw handle
+// index Rapier recycles as soon as a slot's generation rolls over.
+fn collider_object_index(colliders: &ColliderSet, registry: &ObjectRegistry, handle: ColliderHandle) -> f32 {
+    colliders
+        .get(handle)
+        .and_then(|collider| collider.parent())
+        .and_then(|body_handle| registry.id(body_handle))
+        .map_or(-1.0, |id| id as f32)
+}
+
+// Contact manifold points are stored in collider-1 local space; shift them
+// into world space using collider 1's current position.
+fn contact_pair_point(colliders: &ColliderSet, contact_pair: &ContactPair) -> Point3<f32> {
+    let local_point = contact_pair
+        .manifolds
+        .first()
+        .and_then(|manifold| manifold.points.first())
+        .map(|point| point.local_p1)
+        .unwrap_or_else(Point3::origin);
+
+    colliders
+        .get(contact_pair.collider1)
+        .map_or(local_point, |collider| collider.position() * local_point)
+}
+
+// Collects collision and contact-force events into `COLLISION_EVENT_QUEUE` so
+// JS can react to impacts for sound effects, sparks, or scoring. Borrows the
+// registry (same pattern as `OneWayHooks` borrowing `collider_behaviors`) so
+// emitted indices are stable `ObjectRegistry` ids rather than raw handles.
+struct CollectingEventHandler<'a> {
+    registry: &'a ObjectRegistry,
+}
+
+impl<'a> EventHandler for CollectingEventHandler<'a> {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        event: CollisionEvent,
+        contact_pair: Option<&ContactPair>,
+    ) {
+        let (collider1, collider2, kind) = match event {
+            CollisionEvent::Started(h1, h2, _) => (h1, h2, EVENT_COLLISION_STARTED),
+            CollisionEvent::Stopped(h1, h2, _) => (h1, h2, EVENT_COLLISION_STOPPED),
+        };
+        let idx1 = collider_object_index(colliders, self.registry, collider1);
+        let idx2 = collider_object_index(colliders, self.registry, collider2);
+        let point = contact_pair
+            .map(|pair| contact_pair_point(colliders, pair))
+            .unwrap_or_else(Point3::origin);
+
+        COLLISION_EVENT_QUEUE.with(|queue| {
+            queue
+                .borrow_mut()
+                .extend_from_slice(&[kind, idx1, idx2, point.x, point.y, point.z, 0.0]);
+        });
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        contact_pair: &ContactPair,
+        total_force_magnitude: Real,
+    ) {
+        let idx1 = collider_object_index(colliders, self.registry, contact_pair.collider1);
+        let idx2 = collider_object_index(colliders, self.registry, contact_pair.collider2);
+        let point = contact_pair_point(colliders, contact_pair);
+
+        COLLISION_EVENT_QUEUE.with(|queue| {
+            queue.borrow_mut().extend_from_slice(&[
+                EVENT_CONTACT_FORCE,
+                idx1,
+                idx2,
+                point.x,
+                point.y,
+                point.z,
+                total_force_magnitude,
+            ]);
+        });
+    }
+}
+
+// Behavior tag for colliders that need special handling in the solver, e.g.
+// one-way platforms that only collide from one side.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColliderBehavior {
+    OneWayPlatform { pass_direction: Vector<Real> },
+}
+
+// Everything needed to reconstruct a `PhysicsWorld` byte-for-byte. The
+// pipeline/query-pipeline/solver are workspace-only scratch state and are
+// deliberately excluded; they're rebuilt fresh on load.
+#[derive(Serialize, Deserialize)]
+struct WorldSnapshot {
+    integration_parameters: IntegrationParameters,
+    island_manager: IslandManager,
+    rigid_body_set: RigidBodySet,
+    collider_set: ColliderSet,
+    impulse_joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
+    object_types: HashMap<RigidBodyHandle, u32>,
+    collider_behaviors: HashMap<ColliderHandle, ColliderBehavior>,
+    registry: ObjectRegistry,
+    fluid_positions: Vec<Point3<f32>>,
+    fluid_velocities: Vec<Vector3<f32>>,
+    // `ColliderCouplingSet`/boundary handles aren't serializable themselves,
+    // so we snapshot which colliders had fluid coupling registered and
+    // rebuild fresh boundaries/couplings for them on `deserialize`.
+    fluid_boundary_colliders: Vec<ColliderHandle>,
+    boid_positions: Vec<Point3<f32>>,
+    boid_velocities: Vec<Vector3<f32>>,
+    boid_goal: Option<Point3<f32>>,
+}
+
+// Assigns a stable, monotonically increasing id to every spawned body and
+// keeps it valid across removal and respawn, unlike a raw `RigidBodyHandle`
+// index which Rapier recycles as soon as the generation rolls over.
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct ObjectRegistry {
+    next_id: u64,
+    id_to_handle: HashMap<u64, RigidBodyHandle>,
+    handle_to_id: HashMap<RigidBodyHandle, u64>,
+}
+
+impl ObjectRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, handle: RigidBodyHandle) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.id_to_handle.insert(id, handle);
+        self.handle_to_id.insert(handle, id);
+        id
+    }
+
+    pub fn unregister(&mut self, handle: RigidBodyHandle) {
+        if let Some(id) = self.handle_to_id.remove(&handle) {
+            self.id_to_handle.remove(&id);
+        }
+    }
+
+    pub fn handle(&self, id: u64) -> Option<RigidBodyHandle> {
+        self.id_to_handle.get(&id).copied()
+    }
+
+    pub fn id(&self, handle: RigidBodyHandle) -> Option<u64> {
+        self.handle_to_id.get(&handle).copied()
+    }
+}
+
+// Implements Rapier's `PhysicsHooks` so one-way platforms can drop solver
+// contacts for bodies approaching from their pass-through side.
+struct OneWayHooks<'a> {
+    behaviors: &'a HashMap<ColliderHandle, ColliderBehavior>,
+}
+
+impl<'a> PhysicsHooks for OneWayHooks<'a> {
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        let behavior = self
+            .behaviors
+            .get(&context.collider1)
+            .or_else(|| self.behaviors.get(&context.collider2));
+        let Some(ColliderBehavior::OneWayPlatform { pass_direction }) = behavior else {
+            return;
+        };
+
+        // Rapier's contact normal always points from collider1 to collider2,
+        // so flip it so it consistently points from the platform to the
+        // other body regardless of which side of the pair it ended up on.
+        let platform_is_collider1 = self.behaviors.contains_key(&context.collider1);
+        let normal = if platform_is_collider1 {
+            *context.normal
+        } else {
+            -*context.normal
+        };
+
+        let other_collider = if platform_is_collider1 {
+            context.collider2
+        } else {
+            context.collider1
+        };
+        let relative_velocity = context
+            .colliders
+            .get(other_collider)
+            .and_then(|c| c.parent())
+            .and_then(|h| context.bodies.get(h))
+            .map_or(Vector::zeros(), |body| *body.linvel());
+
+        // The other body is on the pass-through side when the platform-to-body
+        // normal points opposite `pass_direction` (i.e. the body is
+        // underneath), and it only passes through while still moving toward
+        // the platform along `pass_direction` (i.e. moving up into it, for
+        // the default up-facing platform). A body already above the platform
+        // and falling onto it has a normal aligned with `pass_direction` and
+        // keeps its contacts, so it lands instead of falling through.
+        let approaching_from_pass_side =
+            relative_velocity.dot(pass_direction) > 0.0 && normal.dot(pass_direction) < 0.0;
+
+        if approaching_from_pass_side {
+            context.solver_contacts.clear();
+        }
+    }
+}
 
 pub struct PhysicsWorld {
     pub pipeline: PhysicsPipeline,
@@ -21,10 +251,35 @@ pub struct PhysicsWorld {
     // Salva Fluid Physics
     pub fluid_pipeline: FluidsPipeline,
     pub fluid_handle: FluidHandle, // Keep track of our main water body
-    
+
+    // Couples rigid colliders into Salva as fluid boundaries so water is
+    // blocked by solids and applies buoyancy/drag back onto them.
+    pub coupling_set: ColliderCouplingSet,
+    // Tracks which boundary backs which collider so `remove_object` can
+    // unregister the coupling instead of leaving a stale boundary behind.
+    pub fluid_boundaries: HashMap<ColliderHandle, BoundaryHandle>,
+
     // Keep track of what we spawned to categorize them for rendering
     // Map RigidBodyHandle -> ObjectType (0: box, 1: sphere)
     pub object_types: HashMap<RigidBodyHandle, u32>,
+
+    // Special-case solver behavior for specific colliders, e.g. one-way platforms.
+    pub collider_behaviors: HashMap<ColliderHandle, ColliderBehavior>,
+
+    // Flattened collision/contact-force records accumulated since the last
+    // `take_collision_events` drain. See `CollectingEventHandler`.
+    pub collision_events: Vec<f32>,
+
+    // Emergent crowd/swarm agents, stepped independently of the rigid body solver.
+    pub boids: BoidSystem,
+
+    // Stable ids for spawned bodies, so JS can track selection across frames
+    // even as bodies are added and removed.
+    pub registry: ObjectRegistry,
+
+    // Shared config for all `move_character` calls: autostep onto small
+    // ledges, and a max climbable / min slide slope.
+    pub character_controller: KinematicCharacterController,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -66,15 +321,115 @@ impl PhysicsWorld {
             
             fluid_pipeline,
             fluid_handle,
-            
+
+            coupling_set: ColliderCouplingSet::new(),
+            fluid_boundaries: HashMap::new(),
+
             object_types: HashMap::new(),
+            collider_behaviors: HashMap::new(),
+            collision_events: Vec::new(),
+            boids: BoidSystem::new(),
+            registry: ObjectRegistry::new(),
+            character_controller: KinematicCharacterController {
+                autostep: Some(CharacterAutostep {
+                    max_height: CharacterLength::Absolute(0.3),
+                    min_width: CharacterLength::Absolute(0.2),
+                    include_dynamic_bodies: true,
+                }),
+                max_slope_climb_angle: 45.0_f32.to_radians(),
+                min_slope_slide_angle: 30.0_f32.to_radians(),
+                ..Default::default()
+            },
+        }
+    }
+
+    // Snapshots everything needed to reproduce the simulation exactly:
+    // rigid bodies, colliders, joints, and fluid particle state. Lets the
+    // orchestrator send a full authoritative state instead of replaying
+    // commands, and gives the client an undo/rollback point.
+    pub fn serialize(&self) -> Vec<u8> {
+        let fluid = self.fluid_pipeline.liquid_world.fluids().get(self.fluid_handle);
+
+        let snapshot = WorldSnapshot {
+            integration_parameters: self.integration_parameters,
+            island_manager: self.island_manager.clone(),
+            rigid_body_set: self.rigid_body_set.clone(),
+            collider_set: self.collider_set.clone(),
+            impulse_joint_set: self.impulse_joint_set.clone(),
+            multibody_joint_set: self.multibody_joint_set.clone(),
+            object_types: self.object_types.clone(),
+            collider_behaviors: self.collider_behaviors.clone(),
+            registry: self.registry.clone(),
+            fluid_positions: fluid.map(|f| f.positions.clone()).unwrap_or_default(),
+            fluid_velocities: fluid.map(|f| f.velocities.clone()).unwrap_or_default(),
+            fluid_boundary_colliders: self.fluid_boundaries.keys().copied().collect(),
+            boid_positions: self.boids.positions.clone(),
+            boid_velocities: self.boids.velocities.clone(),
+            boid_goal: self.boids.goal,
+        };
+
+        bincode::serialize(&snapshot).unwrap_or_default()
+    }
+
+    // Restores a snapshot produced by `serialize`. The pipeline, query
+    // pipeline, and CCD solver are workspace-only scratch state, so they're
+    // rebuilt fresh rather than round-tripped. Returns false on malformed input.
+    pub fn deserialize(&mut self, bytes: &[u8]) -> bool {
+        let Ok(snapshot) = bincode::deserialize::<WorldSnapshot>(bytes) else {
+            return false;
+        };
+
+        self.integration_parameters = snapshot.integration_parameters;
+        self.island_manager = snapshot.island_manager;
+        self.rigid_body_set = snapshot.rigid_body_set;
+        self.collider_set = snapshot.collider_set;
+        self.impulse_joint_set = snapshot.impulse_joint_set;
+        self.multibody_joint_set = snapshot.multibody_joint_set;
+        self.object_types = snapshot.object_types;
+        self.collider_behaviors = snapshot.collider_behaviors;
+        self.registry = snapshot.registry;
+
+        self.pipeline = PhysicsPipeline::new();
+        self.query_pipeline = QueryPipeline::new();
+        self.broad_phase = BroadPhase::new();
+        self.narrow_phase = NarrowPhase::new();
+        self.ccd_solver = CCDSolver::new();
+
+        if let Some(fluid) = self.fluid_pipeline.liquid_world.fluids_mut().get_mut(self.fluid_handle) {
+            fluid.positions = snapshot.fluid_positions;
+            fluid.velocities = snapshot.fluid_velocities;
         }
+
+        // `coupling_set`/`fluid_boundaries` reference boundary handles and a
+        // collider set that no longer match what was just swapped in, so
+        // drop the stale couplings and re-register fresh ones for every
+        // collider the snapshot says had fluid coupling.
+        for (&collider_handle, &boundary_handle) in self.fluid_boundaries.iter() {
+            self.coupling_set.unregister_coupling(collider_handle);
+            self.fluid_pipeline.liquid_world.remove_boundary(boundary_handle);
+        }
+        self.fluid_boundaries.clear();
+        for collider_handle in snapshot.fluid_boundary_colliders {
+            if self.collider_set.get(collider_handle).is_some() {
+                self.register_fluid_coupling(collider_handle);
+            }
+        }
+
+        self.boids.positions = snapshot.boid_positions;
+        self.boids.velocities = snapshot.boid_velocities;
+        self.boids.goal = snapshot.boid_goal;
+
+        true
     }
 
     pub fn step(&mut self) {
-        let physics_hooks = ();
-        let event_handler = ();
-        
+        let physics_hooks = OneWayHooks {
+            behaviors: &self.collider_behaviors,
+        };
+        let event_handler = CollectingEventHandler {
+            registry: &self.registry,
+        };
+
         // Update query pipeline
         self.query_pipeline.update(&self.rigid_body_set, &self.collider_set);
 
@@ -95,43 +450,155 @@ impl PhysicsWorld {
             &event_handler,
         );
         
-        // Step Fluid Physics
+        // Step Fluid Physics, coupled to the rigid bodies registered in
+        // `coupling_set` so water is blocked by solids and pushes back on them.
         // Note: FluidsPipeline::step takes dt. Rapier uses integration_parameters.dt
         let dt = self.integration_parameters.dt;
-        self.fluid_pipeline.step(
+        self.fluid_pipeline.step_with_coupling(
             &self.gravity,
             dt,
             &self.collider_set,
-            &mut self.rigid_body_set,
+            &mut self.coupling_set.as_manager_mut(&self.collider_set, &mut self.rigid_body_set),
         );
+
+        COLLISION_EVENT_QUEUE.with(|queue| {
+            self.collision_events.extend(queue.borrow_mut().drain(..));
+        });
+
+        self.boids.step(dt);
+    }
+
+    pub fn spawn_boids(&mut self, x: f32, y: f32, z: f32, count: u32) {
+        self.boids.spawn(x, y, z, count);
+    }
+
+    // Drains accumulated collision/contact-force records so JS can react to
+    // impacts (sound effects, sparks, scoring). See `CollectingEventHandler`
+    // for the flattened [kind, idx1, idx2, px, py, pz, magnitude] layout.
+    pub fn take_collision_events(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.collision_events)
     }
 
     pub fn spawn_box(&mut self, x: f32, y: f32, z: f32) {
         let rigid_body = RigidBodyBuilder::dynamic()
             .translation(vector![x, y, z])
             .build();
-        let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).restitution(0.7).build();
+        let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5)
+            .restitution(0.7)
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .build();
         let body_handle = self.rigid_body_set.insert(rigid_body);
-        self.collider_set.insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
+        let collider_handle =
+            self.collider_set
+                .insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
         self.object_types.insert(body_handle, 0); // 0 = Box
+        self.register_fluid_coupling(collider_handle);
+        self.registry.register(body_handle);
     }
-    
+
     pub fn spawn_floor(&mut self) {
         let rigid_body = RigidBodyBuilder::fixed().build();
-        let collider = ColliderBuilder::cuboid(100.0, 0.1, 100.0).build();
+        let collider = ColliderBuilder::cuboid(100.0, 0.1, 100.0)
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .build();
         let body_handle = self.rigid_body_set.insert(rigid_body);
-        self.collider_set.insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
+        let collider_handle =
+            self.collider_set
+                .insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
         self.object_types.insert(body_handle, 0); // Floor is also a box shape
+        self.register_fluid_coupling(collider_handle);
+        self.registry.register(body_handle);
+    }
+
+    // Registers `collider_handle` as a Salva fluid boundary so it blocks and
+    // is pushed by fluid particles. Tracked in `fluid_boundaries` so
+    // `remove_object` can unregister it again.
+    fn register_fluid_coupling(&mut self, collider_handle: ColliderHandle) {
+        let boundary_handle = self
+            .fluid_pipeline
+            .liquid_world
+            .add_boundary(Boundary::new(Vec::new()));
+        self.coupling_set.register_coupling(
+            boundary_handle,
+            collider_handle,
+            ColliderSampling::DynamicContactSampling,
+        );
+        self.fluid_boundaries.insert(collider_handle, boundary_handle);
+    }
+
+    // Removes a spawned body along with its colliders and any fluid coupling
+    // boundaries they registered, avoiding stale boundaries after despawn.
+    pub fn remove_object(&mut self, body_handle: RigidBodyHandle) {
+        if let Some(body) = self.rigid_body_set.get(body_handle) {
+            for &collider_handle in body.colliders() {
+                if let Some(boundary_handle) = self.fluid_boundaries.remove(&collider_handle) {
+                    self.coupling_set.unregister_coupling(collider_handle);
+                    self.fluid_pipeline.liquid_world.remove_boundary(boundary_handle);
+                }
+                self.collider_behaviors.remove(&collider_handle);
+            }
+        }
+
+        self.rigid_body_set.remove(
+            body_handle,
+            &mut self.island_manager,
+            &mut self.collider_set,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            true,
+        );
+        self.object_types.remove(&body_handle);
+        self.registry.unregister(body_handle);
+    }
+
+    // Removes a spawned body by its stable id (see `ObjectRegistry`). Returns
+    // false if `id` doesn't refer to a currently spawned body.
+    pub fn despawn(&mut self, id: u64) -> bool {
+        let Some(body_handle) = self.registry.handle(id) else {
+            return false;
+        };
+        self.remove_object(body_handle);
+        true
+    }
+
+    // A thin fixed platform that bodies pass through when they come from the
+    // side opposite (nx, ny, nz) moving along it, but land on from the side
+    // (nx, ny, nz) points to, e.g. a jump-through ledge.
+    pub fn spawn_one_way_platform(&mut self, x: f32, y: f32, z: f32, nx: f32, ny: f32, nz: f32) {
+        let pass_direction = vector![nx, ny, nz].normalize();
+        let rigid_body = RigidBodyBuilder::fixed()
+            .translation(vector![x, y, z])
+            .build();
+        let collider = ColliderBuilder::cuboid(1.0, 0.1, 1.0)
+            .active_hooks(ActiveHooks::MODIFY_SOLVER_CONTACTS)
+            .build();
+        let body_handle = self.rigid_body_set.insert(rigid_body);
+        let collider_handle =
+            self.collider_set
+                .insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
+        self.object_types.insert(body_handle, 0); // Render as a box
+        self.collider_behaviors.insert(
+            collider_handle,
+            ColliderBehavior::OneWayPlatform { pass_direction },
+        );
+        self.registry.register(body_handle);
     }
 
     pub fn spawn_sphere(&mut self, x: f32, y: f32, z: f32) {
         let rigid_body = RigidBodyBuilder::dynamic()
             .translation(vector![x, y, z])
             .build();
-        let collider = ColliderBuilder::ball(0.5).restitution(0.7).build();
+        let collider = ColliderBuilder::ball(0.5)
+            .restitution(0.7)
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .build();
         let body_handle = self.rigid_body_set.insert(rigid_body);
-        self.collider_set.insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
+        let collider_handle =
+            self.collider_set
+                .insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
         self.object_types.insert(body_handle, 1); // 1 = Sphere
+        self.register_fluid_coupling(collider_handle);
+        self.registry.register(body_handle);
     }
 
     pub fn spawn_liquid(&mut self, x: f32, y: f32, z: f32) {
@@ -158,7 +625,65 @@ impl PhysicsWorld {
         fluid.add_particles(&particles, None);
     }
 
-    pub fn cast_ray(&self, origin_x: f32, origin_y: f32, origin_z: f32, dir_x: f32, dir_y: f32, dir_z: f32) -> Option<u32> {
+    // Spawns a kinematic capsule driven entirely through `move_character`,
+    // giving the sandbox a walkable avatar instead of impulse-only interaction.
+    pub fn spawn_character(&mut self, x: f32, y: f32, z: f32) -> u64 {
+        let rigid_body = RigidBodyBuilder::kinematic_position_based()
+            .translation(vector![x, y, z])
+            .build();
+        let collider = ColliderBuilder::capsule_y(0.5, 0.3).build();
+        let body_handle = self.rigid_body_set.insert(rigid_body);
+        self.collider_set
+            .insert_with_parent(collider, body_handle, &mut self.rigid_body_set);
+        self.object_types.insert(body_handle, 4); // 4 = Character
+        self.registry.register(body_handle)
+    }
+
+    // Moves the character by the desired translation, corrected for walls,
+    // slopes, and small ledges by Rapier's `KinematicCharacterController`.
+    // Returns whether the character ended the move grounded.
+    pub fn move_character(&mut self, id: u64, dx: f32, dy: f32, dz: f32) -> bool {
+        let Some(body_handle) = self.registry.handle(id) else {
+            return false;
+        };
+        let Some(collider_handle) = self
+            .rigid_body_set
+            .get(body_handle)
+            .and_then(|body| body.colliders().first().copied())
+        else {
+            return false;
+        };
+        let Some(collider) = self.collider_set.get(collider_handle) else {
+            return false;
+        };
+
+        let shape = collider.shape();
+        let start_pos = *collider.position();
+        let dt = self.integration_parameters.dt;
+        let filter = QueryFilter::default().exclude_rigid_body(body_handle);
+
+        let output = self.character_controller.move_shape(
+            dt,
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.query_pipeline,
+            shape,
+            &start_pos,
+            vector![dx, dy, dz],
+            filter,
+            |_collision| {},
+        );
+
+        if let Some(body) = self.rigid_body_set.get_mut(body_handle) {
+            body.set_next_kinematic_translation(start_pos.translation.vector + output.effective_translation);
+        }
+
+        output.grounded
+    }
+
+    // Returns the stable id (see `ObjectRegistry`) of the body under the ray,
+    // not a raw handle index, so it stays valid as other bodies are removed.
+    pub fn cast_ray(&self, origin_x: f32, origin_y: f32, origin_z: f32, dir_x: f32, dir_y: f32, dir_z: f32) -> Option<u64> {
         let ray = Ray::new(
             point![origin_x, origin_y, origin_z],
             vector![dir_x, dir_y, dir_z],
@@ -167,46 +692,27 @@ impl PhysicsWorld {
         let solid = true;
         let query_filter = QueryFilter::default().groups(InteractionGroups::all());
 
-        if let Some((handle, _toi)) = self.query_pipeline.cast_ray(
+        let (collider_handle, _toi) = self.query_pipeline.cast_ray(
             &self.rigid_body_set,
             &self.collider_set,
             &ray,
             max_toi,
             solid,
             query_filter,
-        ) {
-            // We return the handle as u32. 
-            // RigidBodyHandle in Rapier is generational index, but we can just use the index part for simplicity if we trust generation match
-            // Or better, we return the index. 
-            // Rapier's handles are (index, generation). 
-            // For now, let's just return the raw index, assuming we won't have generation conflicts in this simple demo.
-            return Some(handle.into_raw_parts().0);
-        }
-        None
+        )?;
+
+        let body_handle = self.collider_set.get(collider_handle)?.parent()?;
+        self.registry.id(body_handle)
     }
-    pub fn apply_impulse(&mut self, handle_idx: u32, x: f32, y: f32, z: f32) {
-        // Reconstruct handle (assuming generation 0 or iterating to find match, but for now we try constructing from raw parts)
-        // Rapier handle is (index, generation). We guess generation 0.
-        // A safer way is to store handles in a map, but we don't have that map inverse.
-        // Let's iterate and find the body with this index.
-        
-        // Actually, rigid_body_set.get_mut takes a RigidBodyHandle.
-        // We need to know the generation.
-        // Hack: Assume we passed the raw parts correctly or find it.
-        
-        // Better approach: Iterate and match index.
-        let mut target_handle = None;
-        for (h, _b) in self.rigid_body_set.iter() {
-            if h.into_raw_parts().0 == handle_idx {
-                target_handle = Some(h);
-                break;
-            }
-        }
-        
-        if let Some(h) = target_handle {
-            if let Some(body) = self.rigid_body_set.get_mut(h) {
-                body.apply_impulse(vector![x, y, z], true);
-            }
+
+    // Looks up the body by its stable id rather than a raw handle index,
+    // so ids survive other bodies being despawned and recreated.
+    pub fn apply_impulse(&mut self, id: u64, x: f32, y: f32, z: f32) {
+        let Some(body_handle) = self.registry.handle(id) else {
+            return;
+        };
+        if let Some(body) = self.rigid_body_set.get_mut(body_handle) {
+            body.apply_impulse(vector![x, y, z], true);
         }
     }
 
@@ -220,18 +726,21 @@ impl PhysicsWorld {
         0.0
     }
 
-    // Returns a flattened list of transforms: [x,y,z, qx,qy,qz,qw, type, ...]
+    // Returns a flattened list of transforms: [x,y,z, qx,qy,qz,qw, type, id, ...].
+    // `id` is the stable `ObjectRegistry` id for rigid bodies, or -1 for
+    // fluid particles and boids, which don't have one.
     pub fn get_render_data(&self) -> Vec<f32> {
         let rigid_count = self.rigid_body_set.len();
-        
-        let mut data = Vec::with_capacity(rigid_count * 8);
-        
+
+        let mut data = Vec::with_capacity(rigid_count * 9);
+
         // Rigid bodies
         for (handle, body) in self.rigid_body_set.iter() {
             let pos = body.translation();
             let rot = body.rotation();
             let obj_type = self.object_types.get(&handle).copied().unwrap_or(0);
-            
+            let id = self.registry.id(handle).map_or(-1.0, |id| id as f32);
+
             data.push(pos.x);
             data.push(pos.y);
             data.push(pos.z);
@@ -240,8 +749,9 @@ impl PhysicsWorld {
             data.push(rot.k);
             data.push(rot.w);
             data.push(obj_type as f32);
+            data.push(id);
         }
-        
+
         // Fluid Particles (type 2)
         for (_handle, fluid) in self.fluid_pipeline.liquid_world.fluids().iter() {
             for particle in &fluid.positions {
@@ -253,9 +763,130 @@ impl PhysicsWorld {
                 data.push(0.0); // qz
                 data.push(1.0); // qw
                 data.push(2.0); // Type 2 = Liquid
+                data.push(-1.0); // no stable id
             }
         }
-        
+
+        // Boids (type 3)
+        for position in &self.boids.positions {
+            data.push(position.x);
+            data.push(position.y);
+            data.push(position.z);
+            data.push(0.0); // qx
+            data.push(0.0); // qy
+            data.push(0.0); // qz
+            data.push(1.0); // qw
+            data.push(3.0); // Type 3 = Boid
+            data.push(-1.0); // no stable id
+        }
+
         data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_way_platform_catches_body_falling_from_above() {
+        let mut world = PhysicsWorld::new();
+        world.spawn_one_way_platform(0.0, 0.0, 0.0, 0.0, 1.0, 0.0);
+
+        let rigid_body = RigidBodyBuilder::dynamic()
+            .translation(vector![0.0, 2.0, 0.0])
+            .build();
+        let collider = ColliderBuilder::cuboid(0.2, 0.2, 0.2).build();
+        let body_handle = world.rigid_body_set.insert(rigid_body);
+        world
+            .collider_set
+            .insert_with_parent(collider, body_handle, &mut world.rigid_body_set);
+
+        for _ in 0..120 {
+            world.step();
+        }
+
+        let y = world.rigid_body_set.get(body_handle).unwrap().translation().y;
+        assert!(
+            y > 0.0,
+            "body falling onto the platform from above should land on it, ended at y={y}"
+        );
+    }
+
+    #[test]
+    fn one_way_platform_lets_body_pass_through_from_below() {
+        let mut world = PhysicsWorld::new();
+        world.spawn_one_way_platform(0.0, 0.0, 0.0, 0.0, 1.0, 0.0);
+
+        // Starting closer with enough velocity that it's still well above 0.5
+        // m/s moving up when it crosses the platform: with gravity at
+        // -9.81 m/s^2 an initial 10 m/s only bleeds off to ~0.19 m/s over the
+        // 1 second (60 steps at the default 1/60 s timestep) this test runs,
+        // so the body clears the platform instead of falling back before
+        // reaching it.
+        let rigid_body = RigidBodyBuilder::dynamic()
+            .translation(vector![0.0, -0.5, 0.0])
+            .linvel(vector![0.0, 10.0, 0.0])
+            .build();
+        let collider = ColliderBuilder::cuboid(0.2, 0.2, 0.2).build();
+        let body_handle = world.rigid_body_set.insert(rigid_body);
+        world
+            .collider_set
+            .insert_with_parent(collider, body_handle, &mut world.rigid_body_set);
+
+        for _ in 0..60 {
+            world.step();
+        }
+
+        let y = world.rigid_body_set.get(body_handle).unwrap().translation().y;
+        assert!(
+            y > 0.5,
+            "body moving up from below should pass through the platform, ended at y={y}"
+        );
+    }
+
+    #[test]
+    fn snapshot_round_trips_rigid_body_fluid_and_boid_state() {
+        let mut world = PhysicsWorld::new();
+        world.spawn_box(0.0, 3.0, 0.0);
+        world.spawn_liquid(0.0, 1.0, 0.0);
+        world.spawn_boids(0.0, 1.0, 0.0, 3);
+
+        for _ in 0..30 {
+            world.step();
+        }
+
+        let bytes = world.serialize();
+
+        let mut restored = PhysicsWorld::new();
+        assert!(restored.deserialize(&bytes));
+
+        assert_eq!(restored.rigid_body_set.len(), world.rigid_body_set.len());
+        for (handle, body) in world.rigid_body_set.iter() {
+            let restored_body = restored
+                .rigid_body_set
+                .get(handle)
+                .expect("rigid body missing after restore");
+            assert_eq!(restored_body.translation(), body.translation());
+        }
+
+        let fluid = world
+            .fluid_pipeline
+            .liquid_world
+            .fluids()
+            .get(world.fluid_handle)
+            .unwrap();
+        let restored_fluid = restored
+            .fluid_pipeline
+            .liquid_world
+            .fluids()
+            .get(restored.fluid_handle)
+            .unwrap();
+        assert_eq!(restored_fluid.positions, fluid.positions);
+        assert_eq!(restored_fluid.velocities, fluid.velocities);
+
+        assert_eq!(restored.boids.positions, world.boids.positions);
+        assert_eq!(restored.boids.velocities, world.boids.velocities);
+        assert_eq!(restored.boids.goal, world.boids.goal);
+    }
+}
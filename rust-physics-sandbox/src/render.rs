@@ -5,12 +5,16 @@ use wasm_bindgen::JsCast;
 use web_sys::HtmlCanvasElement;
 use bytemuck::{Pod, Zeroable};
 use crate::physics::PhysicsWorld;
+use crate::hdr::HdrPipeline;
+use crate::texture::Atlas;
+use crate::mesh::Mesh;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct Vertex {
-    position: [f32; 3],
-    normal: [f32; 3],
+pub(crate) struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
 }
 
 #[repr(C)]
@@ -19,6 +23,10 @@ struct InstanceRaw {
     model_pos: [f32; 3],
     model_rot: [f32; 4],
     obj_type: f32,
+    // Original index into `physics.get_render_data()`, carried through so
+    // `fs_id` still reports it after instances are reordered into per-mesh
+    // groups (the `instance_index` builtin resets per `draw_indexed` call).
+    instance_id: f32,
 }
 
 #[repr(C)]
@@ -27,37 +35,52 @@ struct CameraUniform {
     view_proj: [[f32; 4]; 4],
 }
 
+// A single directional light (the sandbox only ever needs one "sun"), plus
+// the camera position needed for the Blinn-Phong specular term. Laid out so
+// each vec3 lands in its own 16-byte slot per WGSL's uniform address space
+// rules, with the trailing scalar filling the rest of that slot.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct LightUniform {
+    direction: [f32; 3],
+    _pad0: f32,
+    color: [f32; 3],
+    ambient: f32,
+    camera_pos: [f32; 3],
+    shininess: f32,
+}
+
 const VERTICES: &[Vertex] = &[
     // Front face
-    Vertex { position: [-0.5, -0.5, 0.5], normal: [0.0, 0.0, 1.0] },
-    Vertex { position: [0.5, -0.5, 0.5], normal: [0.0, 0.0, 1.0] },
-    Vertex { position: [0.5, 0.5, 0.5], normal: [0.0, 0.0, 1.0] },
-    Vertex { position: [-0.5, 0.5, 0.5], normal: [0.0, 0.0, 1.0] },
+    Vertex { position: [-0.5, -0.5, 0.5], normal: [0.0, 0.0, 1.0], tex_coords: [0.0, 1.0] },
+    Vertex { position: [0.5, -0.5, 0.5], normal: [0.0, 0.0, 1.0], tex_coords: [1.0, 1.0] },
+    Vertex { position: [0.5, 0.5, 0.5], normal: [0.0, 0.0, 1.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [-0.5, 0.5, 0.5], normal: [0.0, 0.0, 1.0], tex_coords: [0.0, 0.0] },
     // Back face
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0] },
-    Vertex { position: [-0.5, 0.5, -0.5], normal: [0.0, 0.0, -1.0] },
-    Vertex { position: [0.5, 0.5, -0.5], normal: [0.0, 0.0, -1.0] },
-    Vertex { position: [0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0] },
+    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0], tex_coords: [0.0, 1.0] },
+    Vertex { position: [-0.5, 0.5, -0.5], normal: [0.0, 0.0, -1.0], tex_coords: [0.0, 0.0] },
+    Vertex { position: [0.5, 0.5, -0.5], normal: [0.0, 0.0, -1.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [0.5, -0.5, -0.5], normal: [0.0, 0.0, -1.0], tex_coords: [1.0, 1.0] },
     // Top face
-    Vertex { position: [-0.5, 0.5, -0.5], normal: [0.0, 1.0, 0.0] },
-    Vertex { position: [-0.5, 0.5, 0.5], normal: [0.0, 1.0, 0.0] },
-    Vertex { position: [0.5, 0.5, 0.5], normal: [0.0, 1.0, 0.0] },
-    Vertex { position: [0.5, 0.5, -0.5], normal: [0.0, 1.0, 0.0] },
+    Vertex { position: [-0.5, 0.5, -0.5], normal: [0.0, 1.0, 0.0], tex_coords: [0.0, 1.0] },
+    Vertex { position: [-0.5, 0.5, 0.5], normal: [0.0, 1.0, 0.0], tex_coords: [0.0, 0.0] },
+    Vertex { position: [0.5, 0.5, 0.5], normal: [0.0, 1.0, 0.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [0.5, 0.5, -0.5], normal: [0.0, 1.0, 0.0], tex_coords: [1.0, 1.0] },
     // Bottom face
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0] },
-    Vertex { position: [0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0] },
-    Vertex { position: [0.5, -0.5, 0.5], normal: [0.0, -1.0, 0.0] },
-    Vertex { position: [-0.5, -0.5, 0.5], normal: [0.0, -1.0, 0.0] },
+    Vertex { position: [-0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0], tex_coords: [0.0, 0.0] },
+    Vertex { position: [0.5, -0.5, -0.5], normal: [0.0, -1.0, 0.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [0.5, -0.5, 0.5], normal: [0.0, -1.0, 0.0], tex_coords: [1.0, 1.0] },
+    Vertex { position: [-0.5, -0.5, 0.5], normal: [0.0, -1.0, 0.0], tex_coords: [0.0, 1.0] },
     // Right face
-    Vertex { position: [0.5, -0.5, -0.5], normal: [1.0, 0.0, 0.0] },
-    Vertex { position: [0.5, 0.5, -0.5], normal: [1.0, 0.0, 0.0] },
-    Vertex { position: [0.5, 0.5, 0.5], normal: [1.0, 0.0, 0.0] },
-    Vertex { position: [0.5, -0.5, 0.5], normal: [1.0, 0.0, 0.0] },
+    Vertex { position: [0.5, -0.5, -0.5], normal: [1.0, 0.0, 0.0], tex_coords: [1.0, 1.0] },
+    Vertex { position: [0.5, 0.5, -0.5], normal: [1.0, 0.0, 0.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [0.5, 0.5, 0.5], normal: [1.0, 0.0, 0.0], tex_coords: [0.0, 0.0] },
+    Vertex { position: [0.5, -0.5, 0.5], normal: [1.0, 0.0, 0.0], tex_coords: [0.0, 1.0] },
     // Left face
-    Vertex { position: [-0.5, -0.5, -0.5], normal: [-1.0, 0.0, 0.0] },
-    Vertex { position: [-0.5, -0.5, 0.5], normal: [-1.0, 0.0, 0.0] },
-    Vertex { position: [-0.5, 0.5, 0.5], normal: [-1.0, 0.0, 0.0] },
-    Vertex { position: [-0.5, 0.5, -0.5], normal: [-1.0, 0.0, 0.0] },
+    Vertex { position: [-0.5, -0.5, -0.5], normal: [-1.0, 0.0, 0.0], tex_coords: [0.0, 1.0] },
+    Vertex { position: [-0.5, -0.5, 0.5], normal: [-1.0, 0.0, 0.0], tex_coords: [1.0, 1.0] },
+    Vertex { position: [-0.5, 0.5, 0.5], normal: [-1.0, 0.0, 0.0], tex_coords: [1.0, 0.0] },
+    Vertex { position: [-0.5, 0.5, -0.5], normal: [-1.0, 0.0, 0.0], tex_coords: [0.0, 0.0] },
 ];
 
 const INDICES: &[u16] = &[
@@ -69,6 +92,110 @@ const INDICES: &[u16] = &[
     20, 21, 22, 22, 23, 20, // Left
 ];
 
+const VERTEX_ATTRIBUTES: &[wgpu::VertexAttribute] = &[
+    wgpu::VertexAttribute {
+        offset: 0,
+        shader_location: 0,
+        format: wgpu::VertexFormat::Float32x3, // position
+    },
+    wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+        shader_location: 1,
+        format: wgpu::VertexFormat::Float32x3, // normal
+    },
+    wgpu::VertexAttribute {
+        offset: (std::mem::size_of::<[f32; 3]>() * 2) as wgpu::BufferAddress,
+        shader_location: 2,
+        format: wgpu::VertexFormat::Float32x2, // tex_coords
+    },
+];
+
+const INSTANCE_ATTRIBUTES: &[wgpu::VertexAttribute] = &[
+    wgpu::VertexAttribute {
+        offset: 0,
+        shader_location: 5,
+        format: wgpu::VertexFormat::Float32x3, // model_pos
+    },
+    wgpu::VertexAttribute {
+        offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+        shader_location: 6,
+        format: wgpu::VertexFormat::Float32x4, // model_rot
+    },
+    wgpu::VertexAttribute {
+        offset: (std::mem::size_of::<[f32; 3]>() + std::mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
+        shader_location: 7,
+        format: wgpu::VertexFormat::Float32, // obj_type
+    },
+    wgpu::VertexAttribute {
+        offset: (std::mem::size_of::<[f32; 3]>() + std::mem::size_of::<[f32; 4]>() + std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+        shader_location: 8,
+        format: wgpu::VertexFormat::Float32, // instance_id
+    },
+];
+
+// The main color pass is 4x MSAA'd for smooth cube silhouettes; the
+// id-picking pass stays single-sampled since its R32Uint texel values must
+// never be blended/resolved.
+const SAMPLE_COUNT: u32 = 4;
+
+// Multisampled color target the scene draws into before being resolved
+// into `hdr`'s single-sample texture. Recreated by `new` and `resize`.
+fn create_msaa_color_texture(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("msaa_color_texture"),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: SAMPLE_COUNT,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32, sample_count: u32, label: &str) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Depth32Float,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+fn create_id_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R32Uint,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        label: Some("id_texture"),
+        view_formats: &[],
+    })
+}
+
+// Shared by the main color pipeline and the id-picking pipeline, since both
+// draw the same geometry/instance streams through `vs_main`.
+fn vertex_buffer_layouts() -> [wgpu::VertexBufferLayout<'static>; 2] {
+    [
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: VERTEX_ATTRIBUTES,
+        },
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: INSTANCE_ATTRIBUTES,
+        },
+    ]
+}
+
 pub struct Renderer {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
@@ -76,19 +203,54 @@ pub struct Renderer {
     config: wgpu::SurfaceConfiguration,
     size: (u32, u32),
     render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
+
+    // Mesh id 0 is always the built-in cube; `register_mesh` appends
+    // imported OBJ meshes and `type_mesh` routes each `obj_type` to one.
+    meshes: Vec<Mesh>,
+    type_mesh: Vec<u32>,
+
     instance_buffer: wgpu::Buffer,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
+    light_buffer: wgpu::Buffer,
+    light_bind_group: wgpu::BindGroup,
+
+    // Multisampled scene color target (resolved into `hdr`) and its
+    // matching multisampled depth buffer; both recreated by `resize`.
+    msaa_texture: wgpu::Texture,
     depth_texture: wgpu::Texture,
+
     instance_capacity: usize,
-    
+
+    // Per-obj_type texture atlas sampled in `fs_main`; starts as a single
+    // white texel until `load_atlas` decodes real art.
+    atlas: Atlas,
+    atlas_bind_group_layout: wgpu::BindGroupLayout,
+
+    // Offscreen HDR target the scene renders into; `render()` tone-maps it
+    // into the swapchain as a final fullscreen pass.
+    hdr: HdrPipeline,
+
+    // R32Uint "object id" target written alongside the color pass, read back
+    // one texel at a time by `pick` for pixel-accurate GPU picking.
+    id_pipeline: wgpu::RenderPipeline,
+    id_texture: wgpu::Texture,
+    // Single-sampled depth buffer for the id pass; kept separate from the
+    // multisampled `depth_texture` since a render pass's attachments must
+    // all share one sample count.
+    id_depth_texture: wgpu::Texture,
+
     // Camera state
     camera_azimuth: f32,
     camera_altitude: f32,
     camera_radius: f32,
     camera_target: [f32; 3],
+
+    // Light state, written into `light_buffer` each frame alongside the
+    // current camera position.
+    light_direction: [f32; 3],
+    light_color: [f32; 3],
+    light_ambient: f32,
 }
 
 impl Renderer {
@@ -176,69 +338,79 @@ impl Renderer {
             label: Some("camera_bind_group"),
         });
 
+        // Light Uniform (directional light + camera position, written each frame)
+        let light_direction = [-0.3, -1.0, -0.2];
+        let light_color = [1.0, 1.0, 0.95];
+        let light_ambient = 0.1;
+        let light_uniform = LightUniform {
+            direction: light_direction,
+            _pad0: 0.0,
+            color: light_color,
+            ambient: light_ambient,
+            camera_pos: [0.0; 3],
+            shininess: 32.0,
+        };
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&[light_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let light_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("light_bind_group_layout"),
+        });
+
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &light_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_buffer.as_entire_binding(),
+            }],
+            label: Some("light_bind_group"),
+        });
+
+        // Atlas bind group (texture + sampler + tiles_per_row uniform), starts
+        // pointing at a 1x1 white placeholder until `load_atlas` is called.
+        let atlas_bind_group_layout = Atlas::bind_group_layout(&device);
+        let atlas = Atlas::placeholder(&device, &queue, &atlas_bind_group_layout);
+
         // Shader
         let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
 
         // Pipeline
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&camera_bind_group_layout],
+            bind_group_layouts: &[&camera_bind_group_layout, &light_bind_group_layout, &atlas_bind_group_layout],
             push_constant_ranges: &[],
         });
 
+        let vertex_layouts = vertex_buffer_layouts();
+
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[
-                    // Vertex buffer layout
-                    wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: wgpu::VertexFormat::Float32x3, // position
-                            },
-                            wgpu::VertexAttribute {
-                                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                                shader_location: 1,
-                                format: wgpu::VertexFormat::Float32x3, // normal
-                            },
-                        ],
-                    },
-                    // Instance buffer layout
-                    wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 5,
-                                format: wgpu::VertexFormat::Float32x3, // model_pos
-                            },
-                            wgpu::VertexAttribute {
-                                offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                                shader_location: 6,
-                                format: wgpu::VertexFormat::Float32x4, // model_rot
-                            },
-                            wgpu::VertexAttribute {
-                                offset: (std::mem::size_of::<[f32; 3]>() + std::mem::size_of::<[f32; 4]>()) as wgpu::BufferAddress,
-                                shader_location: 7,
-                                format: wgpu::VertexFormat::Float32, // obj_type
-                            },
-                        ],
-                    },
-                ],
+                buffers: &vertex_layouts,
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
+                // Scene draws into the HDR offscreen target, not the sRGB
+                // swapchain directly; `hdr` tone-maps it in afterwards.
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format: wgpu::TextureFormat::Rgba16Float,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -259,21 +431,66 @@ impl Renderer {
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState::default(),
+            multisample: wgpu::MultisampleState {
+                count: SAMPLE_COUNT,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
             multiview: None,
         });
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
+        // Id-picking pipeline: same geometry/camera as the color pass, but
+        // writes `instance_index + 1` to an R32Uint target instead of shading.
+        // Single-sampled (ids must never be resolved/blended) with its own
+        // depth buffer, since a render pass's attachments must share one
+        // sample count and the color pass's depth buffer is now multisampled.
+        let id_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Id Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout],
+            push_constant_ranges: &[],
         });
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(INDICES),
-            usage: wgpu::BufferUsages::INDEX,
+        let id_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Id Pipeline"),
+            layout: Some(&id_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &vertex_layouts,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_id",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::R32Uint,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
         });
+
+        // Mesh id 0: the built-in cube, always present so every obj_type
+        // renders even before any `register_mesh` call.
+        let meshes = vec![Mesh::from_raw(&device, VERTICES, INDICES)];
+        let type_mesh = Vec::new();
         
         // Initial instance buffer (empty or capacity 100)
         let instance_capacity = 100;
@@ -284,20 +501,12 @@ impl Renderer {
             mapped_at_creation: false,
         });
         
-        let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: config.width,
-                height: config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: Some("depth_texture"),
-            view_formats: &[],
-        });
+        let msaa_texture = create_msaa_color_texture(&device, config.width, config.height, wgpu::TextureFormat::Rgba16Float);
+        let depth_texture = create_depth_texture(&device, config.width, config.height, SAMPLE_COUNT, "depth_texture");
+        let id_texture = create_id_texture(&device, config.width, config.height);
+        let id_depth_texture = create_depth_texture(&device, config.width, config.height, 1, "id_depth_texture");
+
+        let hdr = HdrPipeline::new(&device, config.width, config.height, surface_format);
 
         Ok(Self {
             surface,
@@ -306,21 +515,110 @@ impl Renderer {
             config,
             size: (width, height),
             render_pipeline,
-            vertex_buffer,
-            index_buffer,
+            meshes,
+            type_mesh,
             instance_buffer,
             camera_buffer,
             camera_bind_group,
+            light_buffer,
+            light_bind_group,
+            msaa_texture,
             depth_texture,
+            id_pipeline,
+            id_texture,
+            id_depth_texture,
             instance_capacity,
-            
+            atlas,
+            atlas_bind_group_layout,
+            hdr,
+
             camera_azimuth: 0.0,
             camera_altitude: 0.5, // radians, slightly looking down
             camera_radius: 20.0,
             camera_target: [0.0, 0.0, 0.0],
+
+            light_direction,
+            light_color,
+            light_ambient,
         })
     }
-    
+
+    // Sets the directional light used for shading. `direction` points the
+    // way the light travels (e.g. down and to the side for a low sun).
+    pub fn set_light(&mut self, direction: [f32; 3], color: [f32; 3], ambient: f32) {
+        self.light_direction = direction;
+        self.light_color = color;
+        self.light_ambient = ambient;
+    }
+
+    // Exposure multiplier applied before ACES tone mapping (1.0 = neutral).
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.hdr.set_exposure(exposure);
+    }
+
+    // Toggles the bright-pass + Gaussian blur bloom stage on the HDR target.
+    pub fn set_bloom_enabled(&mut self, enabled: bool) {
+        self.hdr.set_bloom_enabled(enabled);
+    }
+
+    // Decodes a PNG/JPEG atlas image, tiled into `tiles_per_row` equal tiles
+    // across its width, and uploads it to replace the placeholder texture.
+    // Each `obj_type` samples its own tile in `fs_main`.
+    pub fn load_atlas(&mut self, bytes: &[u8], tiles_per_row: u32) -> Result<(), JsValue> {
+        self.atlas = Atlas::from_bytes(&self.device, &self.queue, bytes, tiles_per_row, &self.atlas_bind_group_layout)
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(())
+    }
+
+    // Parses an OBJ mesh and appends it to the mesh table, returning its
+    // mesh id. Pair with `set_mesh_for_type` to draw an `obj_type` with it
+    // instead of the default cube.
+    pub fn register_mesh(&mut self, obj_bytes: &[u8]) -> Result<u32, JsValue> {
+        let mesh = Mesh::from_obj_bytes(&self.device, obj_bytes).map_err(|e| JsValue::from_str(&e))?;
+        self.meshes.push(mesh);
+        Ok((self.meshes.len() - 1) as u32)
+    }
+
+    // Routes every instance of `obj_type` to draw with `mesh_id` (from
+    // `register_mesh`) instead of the default cube.
+    pub fn set_mesh_for_type(&mut self, obj_type: u32, mesh_id: u32) {
+        let obj_type = obj_type as usize;
+        if obj_type >= self.type_mesh.len() {
+            self.type_mesh.resize(obj_type + 1, 0);
+        }
+        self.type_mesh[obj_type] = mesh_id;
+    }
+
+    // Cube (mesh id 0) unless `set_mesh_for_type` assigned this `obj_type`
+    // a registered mesh. Falls back to 0 for an out-of-range mesh id too,
+    // so a stale assignment after mesh table changes never panics.
+    fn mesh_for_type(&self, obj_type: f32) -> usize {
+        let obj_type = obj_type.max(0.0) as usize;
+        let mesh_id = self.type_mesh.get(obj_type).copied().unwrap_or(0) as usize;
+        if mesh_id < self.meshes.len() { mesh_id } else { 0 }
+    }
+
+    // Reconfigures the surface and recreates every size-dependent target
+    // (MSAA color + depth, id pass, HDR/bloom chain) for the new dimensions.
+    // Must be called whenever the canvas is resized, or the depth buffer and
+    // aspect ratio used in `render()` drift out of sync with the swapchain.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.size = (width, height);
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(&self.device, &self.config);
+
+        self.msaa_texture = create_msaa_color_texture(&self.device, width, height, wgpu::TextureFormat::Rgba16Float);
+        self.depth_texture = create_depth_texture(&self.device, width, height, SAMPLE_COUNT, "depth_texture");
+        self.id_texture = create_id_texture(&self.device, width, height);
+        self.id_depth_texture = create_depth_texture(&self.device, width, height, 1, "id_depth_texture");
+        self.hdr.resize(&self.device, width, height);
+    }
+
     pub fn update_camera(&mut self, dx: f32, dy: f32, zoom: f32) {
         // Sensitivity factors
         let rotate_speed = 0.005;
@@ -370,21 +668,101 @@ impl Renderer {
         (near, dir)
     }
 
+    // Pixel-accurate pick against the id texture written by the last
+    // `render()` call. Returns the instance index at (x, y), or `None` for
+    // background/out-of-bounds. Exact where `get_ray_from_screen` + a
+    // physics raycast is ambiguous (overlapping or fast-moving bodies).
+    pub async fn pick(&self, x: u32, y: u32) -> Option<u32> {
+        if x >= self.config.width || y >= self.config.height {
+            return None;
+        }
+
+        // One u32 texel, padded out to wgpu's copy row alignment.
+        let bytes_per_row = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick_staging_buffer"),
+            size: bytes_per_row as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("pick_encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.id_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+        self.queue.submit(iter::once(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.receive().await?.ok()?;
+
+        let id_plus_one = {
+            let data = slice.get_mapped_range();
+            u32::from_le_bytes(data[0..4].try_into().unwrap())
+        };
+        staging_buffer.unmap();
+
+        if id_plus_one == 0 {
+            None
+        } else {
+            Some(id_plus_one - 1)
+        }
+    }
+
     pub fn render(&mut self, physics: &PhysicsWorld) {
         // 1. Prepare Instance Data
-        let render_data = physics.get_render_data(); // [x,y,z, qx,qy,qz,qw, type, ...]
-        let instance_count = render_data.len() / 8;
-        
-        let mut instances = Vec::with_capacity(instance_count);
+        let render_data = physics.get_render_data(); // [x,y,z, qx,qy,qz,qw, type, id, ...]
+        let instance_count = render_data.len() / 9;
+
+        // Bucket instances by mesh id so each mesh can be drawn with its own
+        // vertex/index buffers in one `draw_indexed` call, instead of every
+        // obj_type rendering as the hardcoded cube.
+        let mut buckets: Vec<Vec<InstanceRaw>> = vec![Vec::new(); self.meshes.len()];
         for i in 0..instance_count {
-            let base = i * 8;
-            instances.push(InstanceRaw {
+            let base = i * 9;
+            let obj_type = render_data[base+7];
+            let mesh_id = self.mesh_for_type(obj_type);
+            buckets[mesh_id].push(InstanceRaw {
                 model_pos: [render_data[base], render_data[base+1], render_data[base+2]],
                 model_rot: [render_data[base+3], render_data[base+4], render_data[base+5], render_data[base+6]],
-                obj_type: render_data[base+7],
+                obj_type,
+                instance_id: i as f32,
+                // render_data[base+8] is the stable object id, not yet consumed by the renderer.
             });
         }
-        
+
+        let mut instances = Vec::with_capacity(instance_count);
+        // (mesh_id, start, count) draw groups, in mesh-id order, over the
+        // concatenated `instances` buffer below.
+        let mut groups: Vec<(usize, u32, u32)> = Vec::new();
+        for (mesh_id, bucket) in buckets.into_iter().enumerate() {
+            if bucket.is_empty() {
+                continue;
+            }
+            groups.push((mesh_id, instances.len() as u32, bucket.len() as u32));
+            instances.extend(bucket);
+        }
+
         // Resize instance buffer if needed
         if instances.len() > self.instance_capacity {
              self.instance_capacity = instances.len() * 2;
@@ -417,13 +795,26 @@ impl Renderer {
         
         self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[CameraUniform { view_proj: view_proj_array }]));
 
+        let light_uniform = LightUniform {
+            direction: self.light_direction,
+            _pad0: 0.0,
+            color: self.light_color,
+            ambient: self.light_ambient,
+            camera_pos: [eye.x, eye.y, eye.z],
+            shininess: 32.0,
+        };
+        self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[light_uniform]));
+
         // 3. Render Pass
         let output = match self.surface.get_current_texture() {
             Ok(tex) => tex,
             Err(_) => return,
         };
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let msaa_view = self.msaa_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let depth_view = self.depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let id_depth_view = self.id_depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let instance_size = std::mem::size_of::<InstanceRaw>() as u64;
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
@@ -433,8 +824,10 @@ impl Renderer {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    // Multisampled scene target, resolved into the HDR
+                    // single-sample texture `hdr.process` tone-maps from.
+                    view: &msaa_view,
+                    resolve_target: Some(self.hdr.view()),
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
                             r: 0.05,
@@ -442,7 +835,7 @@ impl Renderer {
                             b: 0.1, // Deep Blue/Black Night Sky
                             a: 1.0,
                         }),
-                        store: wgpu::StoreOp::Store,
+                        store: wgpu::StoreOp::Discard,
                     },
                 })],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
@@ -459,12 +852,65 @@ impl Renderer {
 
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(0..(instances.len() * std::mem::size_of::<InstanceRaw>()) as u64));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..instances.len() as u32);
+            render_pass.set_bind_group(1, &self.light_bind_group, &[]);
+            render_pass.set_bind_group(2, self.atlas.bind_group(), &[]);
+
+            for &(mesh_id, start, count) in &groups {
+                let mesh = &self.meshes[mesh_id];
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(start as u64 * instance_size..(start + count) as u64 * instance_size));
+                render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..mesh.index_count, 0, 0..count);
+            }
         }
 
+        // Id-picking pass: same draw, writes instance ids instead of color.
+        // Depth-tested against its own single-sample depth buffer so
+        // occluded instances don't win the pick.
+        {
+            let id_view = self.id_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let mut id_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Id Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &id_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    // Own single-sample depth buffer: the color pass's depth
+                    // is now multisampled and can't be reused by this
+                    // single-sampled pass, so this draws its own depth from
+                    // scratch (same geometry/camera, so occlusion matches).
+                    view: &id_depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            id_pass.set_pipeline(&self.id_pipeline);
+            id_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+
+            for &(mesh_id, start, count) in &groups {
+                let mesh = &self.meshes[mesh_id];
+                id_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                id_pass.set_vertex_buffer(1, self.instance_buffer.slice(start as u64 * instance_size..(start + count) as u64 * instance_size));
+                id_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                id_pass.draw_indexed(0..mesh.index_count, 0, 0..count);
+            }
+        }
+
+        // Bright-pass + blur + ACES tone map the HDR target into the real
+        // sRGB swapchain texture.
+        self.hdr.process(&self.device, &self.queue, &mut encoder, &view);
+
         self.queue.submit(iter::once(encoder.finish()));
         output.present();
     }